@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{Error, Holiday, CACHE_FADEOUT};
+
+/// A cached list of holidays, tagged with enough metadata to validate it.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CachedHoliday {
+    /// when this cached page was fetched, for fadeout
+    pub fetched: OffsetDateTime,
+    pub year: i32,
+    /// note that this is only ever lowercase
+    pub country_code: String,
+    pub holidays: Vec<Holiday>,
+}
+
+/// A backend capable of storing and retrieving holiday lists keyed by
+/// `(year, country)`.
+///
+/// Implementors only need to provide the raw storage in [`load_raw`] and
+/// [`store`]; the fadeout/staleness logic lives in the default [`load`]
+/// implementation so every backend honours [`CACHE_FADEOUT`] identically.
+///
+/// [`load_raw`]: Cache::load_raw
+/// [`load`]: Cache::load
+/// [`store`]: Cache::store
+pub trait Cache {
+    /// Retrieve the raw cached entry for `(year, country)`, if one exists,
+    /// without applying any fadeout or validity checks.
+    fn load_raw(&self, year: i32, country: &str) -> Result<Option<CachedHoliday>, Error>;
+
+    /// Persist `holidays` for `(year, country)`, recording `fetched` as the
+    /// moment the data was retrieved.
+    fn store(
+        &self,
+        year: i32,
+        country: &str,
+        holidays: &[Holiday],
+        fetched: OffsetDateTime,
+    ) -> Result<(), Error>;
+
+    /// Retrieve the holidays for `(year, country)` if a fresh, matching entry
+    /// is cached.
+    ///
+    /// An entry is considered usable only when its metadata matches the
+    /// request and it was fetched within the last [`CACHE_FADEOUT`]; otherwise
+    /// `None` is returned and the caller is expected to refetch.
+    fn load(&self, year: i32, country: &str) -> Result<Option<Vec<Holiday>>, Error> {
+        match self.load_raw(year, country)? {
+            Some(cache)
+                if cache.year == year
+                    && cache.country_code == country
+                    && cache.fetched + CACHE_FADEOUT >= OffsetDateTime::now_utc() =>
+            {
+                Ok(Some(cache.holidays))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The default filesystem cache: one pretty-printed JSON file per year and
+/// country under the platform cache directory.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FsCache;
+
+impl FsCache {
+    fn path(year: i32, country_code: &str) -> Result<PathBuf, Error> {
+        Ok(dirs::cache_dir()
+            .ok_or(Error::NoCacheDir)?
+            .join("holidate")
+            .join(country_code)
+            .join(format!("{year}.json")))
+    }
+}
+
+impl Cache for FsCache {
+    fn load_raw(&self, year: i32, country: &str) -> Result<Option<CachedHoliday>, Error> {
+        let path = Self::path(year, country)?;
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let reader = std::io::BufReader::new(file);
+        Ok(Some(serde_json::from_reader(reader)?))
+    }
+
+    fn store(
+        &self,
+        year: i32,
+        country: &str,
+        holidays: &[Holiday],
+        fetched: OffsetDateTime,
+    ) -> Result<(), Error> {
+        let path = Self::path(year, country)?;
+        let dir = path
+            .parent()
+            .expect("FsCache::path never returns root directory");
+        std::fs::create_dir_all(dir)?;
+        write_json(&path, &borrowed(year, country, holidays, fetched))?;
+        Ok(())
+    }
+}
+
+/// Distinguishes concurrent temp files written by this process; combined with
+/// the pid it keeps each writer's scratch file distinct.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A content-addressable filesystem cache: entries live in a flat directory
+/// under a filename derived from a hash of `(year, country)`, and are written
+/// via a temporary file and atomic rename so concurrent writers can't observe
+/// or produce a half-written page.
+#[derive(Debug, Clone)]
+pub struct ContentAddressableCache {
+    root: PathBuf,
+}
+
+impl ContentAddressableCache {
+    /// Construct a content-addressable cache rooted at the platform cache
+    /// directory.
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self::with_root(
+            dirs::cache_dir()
+                .ok_or(Error::NoCacheDir)?
+                .join("holidate")
+                .join("cas"),
+        ))
+    }
+
+    /// Construct a content-addressable cache rooted at `root`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        ContentAddressableCache { root: root.into() }
+    }
+
+    fn path(&self, year: i32, country: &str) -> PathBuf {
+        self.root.join(format!("{:016x}.json", key_hash(year, country)))
+    }
+}
+
+impl Cache for ContentAddressableCache {
+    fn load_raw(&self, year: i32, country: &str) -> Result<Option<CachedHoliday>, Error> {
+        let file = match std::fs::File::open(self.path(year, country)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let reader = std::io::BufReader::new(file);
+        Ok(Some(serde_json::from_reader(reader)?))
+    }
+
+    fn store(
+        &self,
+        year: i32,
+        country: &str,
+        holidays: &[Holiday],
+        fetched: OffsetDateTime,
+    ) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.root)?;
+        let path = self.path(year, country);
+
+        // Serialize to a temp file first, then atomically rename it into place:
+        // a reader either sees the previous page or the new one, never a
+        // truncated write. The temp name is unique per writer (pid plus a
+        // process-local counter) so two concurrent writers of the same key
+        // can't clobber each other's scratch file before the rename.
+        let tmp = self.root.join(format!(
+            "{:016x}.{}.{}.tmp",
+            key_hash(year, country),
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+        ));
+        write_json(&tmp, &borrowed(year, country, holidays, fetched))?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// An in-memory cache, handy for tests and for callers that explicitly don't
+/// want to touch the filesystem.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<(i32, String), CachedHoliday>>,
+}
+
+impl MemoryCache {
+    /// Construct an empty in-memory cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Cache for MemoryCache {
+    fn load_raw(&self, year: i32, country: &str) -> Result<Option<CachedHoliday>, Error> {
+        let entries = self.entries.lock().expect("MemoryCache mutex poisoned");
+        Ok(entries
+            .get(&(year, country.to_string()))
+            .map(|cache| CachedHoliday {
+                fetched: cache.fetched,
+                year: cache.year,
+                country_code: cache.country_code.clone(),
+                holidays: clone_holidays(&cache.holidays),
+            }))
+    }
+
+    fn store(
+        &self,
+        year: i32,
+        country: &str,
+        holidays: &[Holiday],
+        fetched: OffsetDateTime,
+    ) -> Result<(), Error> {
+        let mut entries = self.entries.lock().expect("MemoryCache mutex poisoned");
+        entries.insert(
+            (year, country.to_string()),
+            CachedHoliday {
+                fetched,
+                year,
+                country_code: country.to_string(),
+                holidays: clone_holidays(holidays),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// A cache which never retains anything: every `load` misses and every `store`
+/// is discarded. Useful for forcing a fresh fetch or in tests which don't care
+/// about caching at all.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DummyCache;
+
+impl Cache for DummyCache {
+    fn load_raw(&self, _year: i32, _country: &str) -> Result<Option<CachedHoliday>, Error> {
+        Ok(None)
+    }
+
+    fn store(
+        &self,
+        _year: i32,
+        _country: &str,
+        _holidays: &[Holiday],
+        _fetched: OffsetDateTime,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A `CachedHoliday`-shaped value that borrows its holidays, so backends can
+/// serialize without cloning the list.
+#[derive(Serialize)]
+struct CachedHolidayRef<'a> {
+    fetched: OffsetDateTime,
+    year: i32,
+    country_code: &'a str,
+    holidays: &'a [Holiday],
+}
+
+fn borrowed<'a>(
+    year: i32,
+    country: &'a str,
+    holidays: &'a [Holiday],
+    fetched: OffsetDateTime,
+) -> CachedHolidayRef<'a> {
+    CachedHolidayRef {
+        fetched,
+        year,
+        country_code: country,
+        holidays,
+    }
+}
+
+fn write_json<T: Serialize>(path: &std::path::Path, value: &T) -> Result<(), Error> {
+    let file = std::fs::File::create(path)?;
+    let writer = std::io::BufWriter::new(file);
+    serde_json::to_writer_pretty(writer, value)?;
+    Ok(())
+}
+
+/// Round-trip a holiday list through serde to clone it, since `Holiday` is only
+/// `Serialize`/`Deserialize` and not `Clone`.
+fn clone_holidays(holidays: &[Holiday]) -> Vec<Holiday> {
+    let json = serde_json::to_value(holidays).expect("holidays always serialize");
+    serde_json::from_value(json).expect("holidays always round-trip")
+}
+
+/// A small, stable FNV-1a hash of `(year, country)` used to name
+/// content-addressable cache files. Stability across runs matters here, so we
+/// can't lean on `std`'s `DefaultHasher`.
+fn key_hash(year: i32, country: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    let mut mix = |byte: u8| {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(PRIME);
+    };
+    for byte in year.to_le_bytes() {
+        mix(byte);
+    }
+    mix(b'/');
+    for byte in country.as_bytes() {
+        mix(*byte);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HolidayType;
+    use time::macros::date;
+    use time::Duration;
+
+    fn sample() -> Vec<Holiday> {
+        named("New Year's Day")
+    }
+
+    fn named(name: &str) -> Vec<Holiday> {
+        vec![Holiday {
+            date: date!(2024 - 01 - 01),
+            name: name.to_string(),
+            counties: Vec::new(),
+            types: vec![HolidayType::Public],
+        }]
+    }
+
+    /// A unique scratch directory for a single test, cleaned up on drop.
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            static COUNTER: AtomicU64 = AtomicU64::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "holidate-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+            ));
+            std::fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn memory_cache_round_trips() {
+        let cache = MemoryCache::new();
+        assert!(cache.load(2024, "us").unwrap().is_none());
+
+        cache
+            .store(2024, "us", &sample(), OffsetDateTime::now_utc())
+            .unwrap();
+
+        let loaded = cache.load(2024, "us").unwrap().expect("just stored");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "New Year's Day");
+    }
+
+    #[test]
+    fn stale_entries_fade_out() {
+        let cache = MemoryCache::new();
+        let stale = OffsetDateTime::now_utc() - CACHE_FADEOUT - Duration::seconds(1);
+        cache.store(2024, "us", &sample(), stale).unwrap();
+
+        // the raw entry is still present, but `load` applies the fadeout.
+        assert!(cache.load_raw(2024, "us").unwrap().is_some());
+        assert!(cache.load(2024, "us").unwrap().is_none());
+    }
+
+    #[test]
+    fn dummy_cache_never_retains() {
+        let cache = DummyCache;
+        cache
+            .store(2024, "us", &sample(), OffsetDateTime::now_utc())
+            .unwrap();
+        assert!(cache.load(2024, "us").unwrap().is_none());
+    }
+
+    #[test]
+    fn content_addressable_round_trips() {
+        let dir = TempDir::new();
+        let cache = ContentAddressableCache::with_root(&dir.0);
+
+        assert!(cache.load(2024, "us").unwrap().is_none());
+        cache
+            .store(2024, "us", &sample(), OffsetDateTime::now_utc())
+            .unwrap();
+
+        let loaded = cache.load(2024, "us").unwrap().expect("just stored");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "New Year's Day");
+    }
+
+    #[test]
+    fn content_addressable_survives_concurrent_overwrite() {
+        let dir = TempDir::new();
+        let cache = ContentAddressableCache::with_root(&dir.0);
+
+        // Many writers racing on the same key: the atomic rename must leave a
+        // single, complete, parseable entry behind — never a torn file.
+        std::thread::scope(|scope| {
+            for i in 0..16 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    cache
+                        .store(
+                            2024,
+                            "us",
+                            &named(&format!("writer {i}")),
+                            OffsetDateTime::now_utc(),
+                        )
+                        .unwrap();
+                });
+            }
+        });
+
+        let loaded = cache.load(2024, "us").unwrap().expect("some writer won");
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded[0].name.starts_with("writer "));
+    }
+}
@@ -0,0 +1,257 @@
+//! Convert Gregorian holiday dates into other calendar systems.
+//!
+//! Every conversion pivots through the [Julian Day Number][jdn]: the Gregorian
+//! date is turned into a JDN with the standard integer formula, then the target
+//! calendar's own arithmetic rules map that JDN back into a `(year, month,
+//! day)` triple. The public surface ([`Calendar`], [`CalendarDate`],
+//! [`convert`]) stays calendar-agnostic so new systems can be slotted in behind
+//! the same API.
+//!
+//! [jdn]: https://en.wikipedia.org/wiki/Julian_day
+
+use std::fmt;
+use std::str::FromStr;
+
+use time::Date;
+
+/// A supported target calendar system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
+#[display(style = "lowercase")]
+pub enum Calendar {
+    Gregorian,
+    Islamic,
+    Hebrew,
+}
+
+impl FromStr for Calendar {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gregorian" => Ok(Calendar::Gregorian),
+            "islamic" | "hijri" => Ok(Calendar::Islamic),
+            "hebrew" | "jewish" => Ok(Calendar::Hebrew),
+            other => Err(format!("unknown calendar {other:?}")),
+        }
+    }
+}
+
+/// A date expressed in some [`Calendar`].
+///
+/// Hebrew months are numbered in the ecclesiastical convention used by the
+/// underlying algorithm, with Nisan as month 1 and Tishri (the new year) as
+/// month 7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CalendarDate {
+    pub calendar: Calendar,
+    pub year: i64,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} ({})",
+            self.year, self.month, self.day, self.calendar
+        )
+    }
+}
+
+/// Convert a Gregorian `date` into the requested `calendar`.
+pub fn convert(date: Date, calendar: Calendar) -> CalendarDate {
+    let jdn = gregorian_to_jdn(date.year() as i64, date.month() as u8, date.day());
+    let (year, month, day) = match calendar {
+        Calendar::Gregorian => (
+            date.year() as i64,
+            date.month() as u8,
+            date.day(),
+        ),
+        Calendar::Islamic => islamic_from_jdn(jdn),
+        Calendar::Hebrew => hebrew_from_jdn(jdn),
+    };
+    CalendarDate {
+        calendar,
+        year,
+        month,
+        day,
+    }
+}
+
+/// The Gregorian-to-JDN conversion (Fliegel–Van Flandern), computed entirely in
+/// integers.
+fn gregorian_to_jdn(year: i64, month: u8, day: u8) -> i64 {
+    let a = (14 - month as i64) / 12;
+    let y = year + 4800 - a;
+    let m = month as i64 + 12 * a - 3;
+    day as i64 + (153 * m + 2) / 5 + 365 * y + y / 4 - y / 100 + y / 400 - 32045
+}
+
+/// The civil (tabular) Islamic calendar's from-JDN algorithm.
+fn islamic_from_jdn(jdn: i64) -> (i64, u8, u8) {
+    // epoch of the civil variant, 16 July 622 CE (Julian) = JDN 1948440.
+    let l = jdn - 1948440 + 10632;
+    let n = (l - 1) / 10631;
+    let l = l - 10631 * n + 354;
+    let j = ((10985 - l) / 5316) * ((50 * l) / 17719) + (l / 5670) * ((43 * l) / 15238);
+    let l = l - ((30 - j) / 15) * ((17719 * j) / 50) - (j / 16) * ((15238 * j) / 43) + 29;
+    let month = (24 * l) / 709;
+    let day = l - (709 * month) / 24;
+    let year = 30 * n + j - 30;
+    (year, month as u8, day as u8)
+}
+
+// The Hebrew conversion follows the arithmetic rules laid out in Dershowitz &
+// Reingold's *Calendrical Calculations*, worked in "fixed" (Rata Die) days. A
+// JDN differs from a fixed day by a constant, so we translate once up front.
+const JDN_TO_FIXED: i64 = 1_721_425;
+const HEBREW_EPOCH: i64 = -1_373_427;
+
+fn hebrew_from_jdn(jdn: i64) -> (i64, u8, u8) {
+    hebrew_from_fixed(jdn - JDN_TO_FIXED)
+}
+
+fn hebrew_leap_year(year: i64) -> bool {
+    (7 * year + 1).rem_euclid(19) < 7
+}
+
+fn last_month_of_hebrew_year(year: i64) -> u8 {
+    if hebrew_leap_year(year) {
+        13
+    } else {
+        12
+    }
+}
+
+fn hebrew_calendar_elapsed_days(year: i64) -> i64 {
+    let months_elapsed = (235 * year - 234) / 19;
+    let parts_elapsed = 12084 + 13753 * months_elapsed;
+    let day = 29 * months_elapsed + parts_elapsed / 25920;
+    if (3 * (day + 1)).rem_euclid(7) < 3 {
+        day + 1
+    } else {
+        day
+    }
+}
+
+fn hebrew_year_length_correction(year: i64) -> i64 {
+    let ny0 = hebrew_calendar_elapsed_days(year - 1);
+    let ny1 = hebrew_calendar_elapsed_days(year);
+    let ny2 = hebrew_calendar_elapsed_days(year + 1);
+    if ny2 - ny1 == 356 {
+        2
+    } else if ny1 - ny0 == 382 {
+        1
+    } else {
+        0
+    }
+}
+
+fn hebrew_new_year(year: i64) -> i64 {
+    HEBREW_EPOCH + hebrew_calendar_elapsed_days(year) + hebrew_year_length_correction(year)
+}
+
+fn days_in_hebrew_year(year: i64) -> i64 {
+    hebrew_new_year(year + 1) - hebrew_new_year(year)
+}
+
+fn long_marheshvan(year: i64) -> bool {
+    matches!(days_in_hebrew_year(year), 355 | 385)
+}
+
+fn short_kislev(year: i64) -> bool {
+    matches!(days_in_hebrew_year(year), 353 | 383)
+}
+
+fn last_day_of_hebrew_month(month: u8, year: i64) -> i64 {
+    match month {
+        2 | 4 | 6 | 10 | 13 => 29,
+        12 if !hebrew_leap_year(year) => 29,
+        8 if !long_marheshvan(year) => 29,
+        9 if short_kislev(year) => 29,
+        _ => 30,
+    }
+}
+
+fn fixed_from_hebrew(month: u8, day: u8, year: i64) -> i64 {
+    let mut result = hebrew_new_year(year) + day as i64 - 1;
+    if month < 7 {
+        for m in 7..=last_month_of_hebrew_year(year) {
+            result += last_day_of_hebrew_month(m, year);
+        }
+        for m in 1..month {
+            result += last_day_of_hebrew_month(m, year);
+        }
+    } else {
+        for m in 7..month {
+            result += last_day_of_hebrew_month(m, year);
+        }
+    }
+    result
+}
+
+fn hebrew_from_fixed(date: i64) -> (i64, u8, u8) {
+    let approx = (98_496 * (date - HEBREW_EPOCH)) / 35_975_351 + 1;
+    let mut year = approx - 1;
+    while hebrew_new_year(year + 1) <= date {
+        year += 1;
+    }
+    let start = if date < fixed_from_hebrew(1, 1, year) {
+        7
+    } else {
+        1
+    };
+    let mut month = start;
+    while date > fixed_from_hebrew(month, last_day_of_hebrew_month(month, year), year) {
+        month += 1;
+    }
+    let day = (date - fixed_from_hebrew(month, 1, year) + 1) as u8;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn gregorian_to_jdn_matches_known_anchor() {
+        // 2000-01-01 is JDN 2451545.
+        assert_eq!(gregorian_to_jdn(2000, 1, 1), 2451545);
+    }
+
+    #[test]
+    fn islamic_epoch_is_one_one_one() {
+        // JDN 1948440 is 1 Muharram 1 AH in the civil (tabular) calendar.
+        assert_eq!(islamic_from_jdn(1948440), (1, 1, 1));
+    }
+
+    #[test]
+    fn converts_into_islamic() {
+        // 2000-01-01 Gregorian is 24 Ramadan 1420 AH (tabular); Ramadan is
+        // month 9.
+        let converted = convert(date!(2000 - 01 - 01), Calendar::Islamic);
+        assert_eq!(
+            (converted.year, converted.month, converted.day),
+            (1420, 9, 24)
+        );
+    }
+
+    #[test]
+    fn converts_into_hebrew() {
+        // 2000-01-01 Gregorian is 23 Tevet 5760; Tevet is month 10 in the
+        // Nisan-first numbering used here.
+        let converted = convert(date!(2000 - 01 - 01), Calendar::Hebrew);
+        assert_eq!(
+            (converted.year, converted.month, converted.day),
+            (5760, 10, 23)
+        );
+    }
+
+    #[test]
+    fn gregorian_is_the_identity() {
+        let converted = convert(date!(2024 - 07 - 04), Calendar::Gregorian);
+        assert_eq!((converted.year, converted.month, converted.day), (2024, 7, 4));
+    }
+}
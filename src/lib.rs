@@ -1,13 +1,22 @@
-use std::path::PathBuf;
+use std::time::Duration as StdDuration;
 
+use rand::Rng;
 use serde::{Deserialize, Deserializer, Serialize};
 use time::{Date, Duration, OffsetDateTime};
 
+mod cache;
+mod calendar;
+
+pub use cache::{
+    Cache, CachedHoliday, ContentAddressableCache, DummyCache, FsCache, MemoryCache,
+};
+pub use calendar::{convert, Calendar, CalendarDate};
+
 /// How long a cached list of holidays is valid for, before hitting the API
 /// again to check for updates.
-const CACHE_FADEOUT: Duration = Duration::hours(24);
+pub const CACHE_FADEOUT: Duration = Duration::hours(24);
 
-#[derive(Debug, parse_display::Display, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display, Deserialize, Serialize)]
 pub enum HolidayType {
     Public,
     Bank,
@@ -17,6 +26,22 @@ pub enum HolidayType {
     Observance,
 }
 
+impl std::str::FromStr for HolidayType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "public" => Ok(HolidayType::Public),
+            "bank" => Ok(HolidayType::Bank),
+            "school" => Ok(HolidayType::School),
+            "authorities" => Ok(HolidayType::Authorities),
+            "optional" => Ok(HolidayType::Optional),
+            "observance" => Ok(HolidayType::Observance),
+            other => Err(format!("unknown holiday type {other:?}")),
+        }
+    }
+}
+
 // The Nager API provides several other fields than these, but we don't care
 // about them for this use case, and `serde_json` conveniently just ignores
 // any fields which aren't present in the struct.
@@ -29,63 +54,82 @@ pub struct Holiday {
     pub types: Vec<HolidayType>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct CachedHoliday {
-    /// when this cached page was fetched, for fadeout
-    fetched: OffsetDateTime,
-    year: i32,
-    /// note that this is only ever lowercase
-    country_code: String,
-    holidays: Vec<Holiday>,
-}
-
-impl CachedHoliday {
-    fn path(year: i32, country_code: &str) -> Result<PathBuf, Error> {
-        Ok(dirs::cache_dir()
-            .ok_or(Error::NoCacheDir)?
-            .join("holidate")
-            .join(country_code)
-            .join(format!("{year}.json")))
-    }
+/// Upper bound on how many years past `relative_to` we'll scan looking for
+/// enough matching holidays before giving up. Without this a filter that
+/// nothing matches would loop forever.
+const MAX_YEARS_TO_SCAN: i32 = 10;
 
-    fn load(year: i32, country_code: &str) -> Option<Vec<Holiday>> {
-        let file = std::fs::File::open(Self::path(year, country_code).ok()?).ok()?;
-        let reader = std::io::BufReader::new(file);
-        let cache: Self = serde_json::from_reader(reader).ok()?;
+/// Criteria applied to holidays before they're counted towards the requested
+/// quantity.
+///
+/// An empty filter matches everything. Each populated field narrows the result:
+/// [`types`] and [`counties`] are allow-lists (a holiday matches if it has at
+/// least one listed type / county), and [`name_contains`] is a
+/// case-insensitive substring match against the holiday name.
+///
+/// [`types`]: HolidayFilter::types
+/// [`counties`]: HolidayFilter::counties
+/// [`name_contains`]: HolidayFilter::name_contains
+#[derive(Debug, Default, Clone)]
+pub struct HolidayFilter {
+    pub types: Vec<HolidayType>,
+    pub counties: Vec<String>,
+    pub name_contains: Option<String>,
+}
 
-        if cache.year != year
-            || cache.country_code != country_code
-            || cache.fetched + CACHE_FADEOUT < OffsetDateTime::now_utc()
+impl HolidayFilter {
+    /// Whether `holiday` satisfies every populated criterion.
+    pub fn matches(&self, holiday: &Holiday) -> bool {
+        if !self.types.is_empty()
+            && !holiday.types.iter().any(|ty| self.types.contains(ty))
         {
-            None
-        } else {
-            Some(cache.holidays)
+            return false;
         }
-    }
 
-    fn store(&self) -> Result<(), Error> {
-        let path = Self::path(self.year, &self.country_code)?;
-        let dir = path
-            .parent()
-            .expect("Self::path never returns root directory");
-        std::fs::create_dir_all(dir)?;
-        let file = std::fs::File::create(path)?;
-        let writer = std::io::BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self)?;
-        Ok(())
+        if !self.counties.is_empty()
+            && !holiday.counties.iter().any(|county| {
+                self.counties
+                    .iter()
+                    .any(|wanted| wanted.eq_ignore_ascii_case(county))
+            })
+        {
+            return false;
+        }
+
+        if let Some(needle) = &self.name_contains {
+            if !holiday
+                .name
+                .to_lowercase()
+                .contains(&needle.to_lowercase())
+            {
+                return false;
+            }
+        }
+
+        true
     }
 }
 
 pub fn next_holidays(
+    cache: &dyn Cache,
     country: &str,
     relative_to: Date,
     quantity: usize,
+    filter: &HolidayFilter,
 ) -> Result<Vec<Holiday>, Error> {
-    let mut year = relative_to.year();
+    let start_year = relative_to.year();
+    let mut year = start_year;
     let mut holidays = Vec::new();
     while holidays.len() < quantity {
-        let mut new_holidays: Vec<Holiday> = get_holidays_cached(year, country)?;
-        new_holidays.retain(|holiday| holiday.date >= relative_to);
+        if year - start_year >= MAX_YEARS_TO_SCAN {
+            return Err(Error::FilterUnsatisfied {
+                wanted: quantity,
+                found: holidays.len(),
+                years: MAX_YEARS_TO_SCAN,
+            });
+        }
+        let mut new_holidays: Vec<Holiday> = get_holidays_cached(cache, year, country)?;
+        new_holidays.retain(|holiday| holiday.date >= relative_to && filter.matches(holiday));
         holidays.extend(new_holidays);
         year += 1;
     }
@@ -97,12 +141,122 @@ fn uri_for(year: i32, country_code: &str) -> String {
     format!("https://date.nager.at/api/v3/publicholidays/{year}/{country_code}")
 }
 
-fn get_holidays_cached(year: i32, country_code: &str) -> Result<Vec<Holiday>, Error> {
+/// Controls how transient API failures are retried.
+///
+/// Failures worth retrying are request timeouts and `5xx`/`429` responses; a
+/// genuine `4xx` (e.g. an unknown country) is a hard error and is never
+/// retried. Between attempts we sleep a full-jitter exponential backoff — for
+/// attempt `n` (0-indexed), a random duration in `[0, base * 2^n]` capped at
+/// `max_backoff` — and honour a `Retry-After` header when the server sends one.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first. One means no retries.
+    pub max_attempts: u32,
+    /// Base of the exponential backoff.
+    pub base: StdDuration,
+    /// Upper bound on any single backoff sleep.
+    pub max_backoff: StdDuration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 3,
+            base: StdDuration::from_millis(200),
+            max_backoff: StdDuration::from_secs(5),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The full-jitter backoff to sleep before the retry following attempt `n`,
+    /// never shorter than `retry_after` if the server asked us to wait.
+    fn backoff(&self, attempt: u32, retry_after: Option<StdDuration>) -> StdDuration {
+        let window = self
+            .base
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+            .min(self.max_backoff);
+        let jitter = StdDuration::from_nanos(
+            rand::thread_rng().gen_range(0..=window.as_nanos() as u64),
+        );
+        jitter.max(retry_after.unwrap_or_default())
+    }
+}
+
+/// Whether a non-success status code is worth retrying: server errors and the
+/// explicit "too many requests" throttle, but not client errors.
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Parse a `Retry-After` header expressed as a number of seconds. The HTTP-date
+/// form is also legal but Nager only ever sends the delay form, so we don't
+/// bother parsing dates.
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<StdDuration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(StdDuration::from_secs)
+}
+
+/// Fetch `url` with the configured retry policy, returning the raw response body
+/// on the first success.
+fn fetch_with_retry(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    retry: &RetryConfig,
+) -> Result<bytes::Bytes, Error> {
+    // always make at least one attempt, even if misconfigured with zero.
+    let attempts = retry.max_attempts.max(1);
+
+    for attempt in 0..attempts {
+        let is_last = attempt + 1 >= attempts;
+        match client.get(url).send() {
+            Ok(response) if response.status().is_success() => return Ok(response.bytes()?),
+            Ok(response) if is_retryable_status(response.status()) => {
+                // a transient server error: retry until we run out of attempts,
+                // then report it as `RetriesExhausted` so the caller can tell it
+                // apart from a first-try hard failure.
+                let retry_after = parse_retry_after(response.headers());
+                let err = response.error_for_status().expect_err("status is an error");
+                if is_last {
+                    return Err(Error::RetriesExhausted(err));
+                }
+                std::thread::sleep(retry.backoff(attempt, retry_after));
+            }
+            // a non-retryable status (e.g. a genuine 4xx): a hard failure.
+            Ok(response) => {
+                response.error_for_status()?;
+                unreachable!("status was already determined not to be a success");
+            }
+            Err(err) if err.is_timeout() => {
+                if is_last {
+                    return Err(Error::RetriesExhausted(err));
+                }
+                std::thread::sleep(retry.backoff(attempt, None));
+            }
+            // a non-timeout transport error: a hard failure.
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    unreachable!("the loop returns on every path when there is at least one attempt");
+}
+
+fn get_holidays_cached(
+    cache: &dyn Cache,
+    year: i32,
+    country_code: &str,
+) -> Result<Vec<Holiday>, Error> {
     // the cache only ever deals with lowercase country codes, so let's compute
     // that here and use it throughout
     let country_code = country_code.to_lowercase();
 
-    if let Some(holidays) = CachedHoliday::load(year, &country_code) {
+    if let Some(holidays) = cache.load(year, &country_code)? {
         return Ok(holidays);
     }
 
@@ -114,11 +268,7 @@ fn get_holidays_cached(year: i32, country_code: &str) -> Result<Vec<Holiday>, Er
         ))
         .build()?;
 
-    let body = client
-        .get(uri_for(year, &country_code))
-        .send()?
-        .error_for_status()?
-        .bytes()?;
+    let body = fetch_with_retry(&client, &uri_for(year, &country_code), &RetryConfig::default())?;
 
     // returning an empty body with a 200 status code isn't the most convenient
     // possible way for the API to indicate that it doesn't know a particular
@@ -127,17 +277,11 @@ fn get_holidays_cached(year: i32, country_code: &str) -> Result<Vec<Holiday>, Er
         return Err(Error::UnknownCountry);
     }
 
-    let holidays = serde_json::from_slice(&body)?;
+    let holidays: Vec<Holiday> = serde_json::from_slice(&body)?;
 
-    let cache = CachedHoliday {
-        fetched: OffsetDateTime::now_utc(),
-        year,
-        country_code,
-        holidays,
-    };
-    cache.store()?;
+    cache.store(year, &country_code, &holidays, OffsetDateTime::now_utc())?;
 
-    Ok(cache.holidays)
+    Ok(holidays)
 }
 
 /// Helper for serde to deserialize a `null` value as its default value.
@@ -158,10 +302,152 @@ pub enum Error {
     UnknownCountry,
     #[error("http problem")]
     Reqwest(#[from] reqwest::Error),
+    #[error("gave up after retrying a transient http failure")]
+    RetriesExhausted(#[source] reqwest::Error),
     #[error("no cache directory on this architecture")]
     NoCacheDir,
     #[error("io error manipulating cache")]
     Io(#[from] std::io::Error),
     #[error("json serialization")]
     Json(#[from] serde_json::Error),
+    #[error("only found {found} of {wanted} matching holidays within {years} years")]
+    FilterUnsatisfied {
+        wanted: usize,
+        found: usize,
+        years: i32,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+    use time::Month;
+
+    fn holiday(date: Date, name: &str, counties: &[&str], types: &[HolidayType]) -> Holiday {
+        Holiday {
+            date,
+            name: name.to_string(),
+            counties: counties.iter().map(|c| c.to_string()).collect(),
+            types: types.to_vec(),
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = HolidayFilter::default();
+        assert!(filter.matches(&holiday(
+            date!(2024 - 01 - 01),
+            "New Year's Day",
+            &[],
+            &[HolidayType::Public],
+        )));
+    }
+
+    #[test]
+    fn filters_by_type_county_and_name() {
+        let holiday = holiday(
+            date!(2024 - 05 - 17),
+            "Constitution Day",
+            &["DE-BY"],
+            &[HolidayType::Public, HolidayType::Bank],
+        );
+
+        // a type allow-list matches when at least one type overlaps.
+        assert!(HolidayFilter {
+            types: vec![HolidayType::Bank],
+            ..Default::default()
+        }
+        .matches(&holiday));
+        assert!(!HolidayFilter {
+            types: vec![HolidayType::School],
+            ..Default::default()
+        }
+        .matches(&holiday));
+
+        // county matching is case-insensitive.
+        assert!(HolidayFilter {
+            counties: vec!["de-by".to_string()],
+            ..Default::default()
+        }
+        .matches(&holiday));
+        assert!(!HolidayFilter {
+            counties: vec!["DE-BE".to_string()],
+            ..Default::default()
+        }
+        .matches(&holiday));
+
+        // name matching is a case-insensitive substring.
+        assert!(HolidayFilter {
+            name_contains: Some("constitution".to_string()),
+            ..Default::default()
+        }
+        .matches(&holiday));
+        assert!(!HolidayFilter {
+            name_contains: Some("labour".to_string()),
+            ..Default::default()
+        }
+        .matches(&holiday));
+    }
+
+    #[test]
+    fn scan_is_bounded_when_nothing_matches() {
+        // Pre-seed every year we'll scan so the bound is exercised without any
+        // network access, then ask for a name that never matches.
+        let cache = MemoryCache::new();
+        let relative_to = date!(2024 - 01 - 01);
+        for offset in 0..=MAX_YEARS_TO_SCAN {
+            let year = relative_to.year() + offset;
+            let date = Date::from_calendar_date(year, Month::June, 1).unwrap();
+            cache
+                .store(
+                    year,
+                    "us",
+                    &[holiday(date, "Independence Day", &[], &[HolidayType::Public])],
+                    OffsetDateTime::now_utc(),
+                )
+                .unwrap();
+        }
+
+        let filter = HolidayFilter {
+            name_contains: Some("no such holiday".to_string()),
+            ..Default::default()
+        };
+        let result = next_holidays(&cache, "us", relative_to, 1, &filter);
+        assert!(matches!(
+            result,
+            Err(Error::FilterUnsatisfied {
+                wanted: 1,
+                years: MAX_YEARS_TO_SCAN,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn filter_applies_before_truncation() {
+        // Two holidays in one year; asking for a single Bank holiday must skip
+        // the earlier Public one rather than returning it and truncating.
+        let cache = MemoryCache::new();
+        let relative_to = date!(2024 - 01 - 01);
+        cache
+            .store(
+                2024,
+                "us",
+                &[
+                    holiday(date!(2024 - 01 - 02), "Public one", &[], &[HolidayType::Public]),
+                    holiday(date!(2024 - 01 - 03), "Bank one", &[], &[HolidayType::Bank]),
+                ],
+                OffsetDateTime::now_utc(),
+            )
+            .unwrap();
+
+        let filter = HolidayFilter {
+            types: vec![HolidayType::Bank],
+            ..Default::default()
+        };
+        let found = next_holidays(&cache, "us", relative_to, 1, &filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "Bank one");
+    }
 }
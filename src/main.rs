@@ -1,9 +1,15 @@
 use std::str::FromStr;
 
-use holidate::Holiday;
+use holidate::{Calendar, FsCache, Holiday, HolidayFilter, HolidayType};
 use itertools::Itertools;
+use serde::Serialize;
 use structopt::StructOpt;
-use time::{macros::format_description, Date};
+use time::{
+    macros::format_description,
+    Date,
+};
+
+mod serve;
 
 #[derive(Debug)]
 struct ParseableDate(Date);
@@ -16,6 +22,33 @@ impl FromStr for ParseableDate {
     }
 }
 
+/// How the retrieved holidays are rendered to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Fixed-width human-readable lines (the default).
+    Table,
+    /// The raw `Vec<Holiday>` as JSON.
+    Json,
+    /// One record per holiday, with comma-joined counties and types.
+    Csv,
+    /// An iCalendar `VCALENDAR` with one `VEVENT` per holiday.
+    Ical,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "ical" => Ok(Format::Ical),
+            other => Err(format!("unknown format {other:?}")),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 struct Options {
     /// Date relative to which we find the next holidays.
@@ -28,10 +61,53 @@ struct Options {
     #[structopt(short, long, default_value = "5")]
     number: u32,
 
+    /// Output format: one of "table", "json", "csv", "ical".
+    #[structopt(short, long, default_value = "table")]
+    format: Format,
+
+    /// Also show each date in another calendar system.
+    ///
+    /// One of "gregorian", "islamic", "hebrew". Only affects the "table"
+    /// format.
+    #[structopt(short, long)]
+    calendar: Option<Calendar>,
+
+    /// Only show holidays of these types (repeat to allow several).
+    ///
+    /// E.g. `--type public --type bank`.
+    #[structopt(long = "type")]
+    types: Vec<HolidayType>,
+
+    /// Only show holidays observed in these counties (repeat to allow several).
+    #[structopt(long)]
+    county: Vec<String>,
+
+    /// Only show holidays whose name contains this substring (case-insensitive).
+    #[structopt(long)]
+    name_contains: Option<String>,
+
     /// Country code for which to look up holidays.
     ///
     /// Must be a member of the list at <https://date.nager.at/Country>.
-    country_code: String,
+    ///
+    /// Required unless a subcommand is given.
+    country_code: Option<String>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Run holidate as a long-running HTTP service.
+    ///
+    /// Exposes `GET /holidays/{country}?from=YYYY-MM-DD&n=5` returning the
+    /// holidays as JSON, with caching and CORS headers.
+    Serve {
+        /// Address to bind the service to.
+        #[structopt(short, long, default_value = "127.0.0.1:8080")]
+        bind: String,
+    },
 }
 
 impl Options {
@@ -61,11 +137,121 @@ fn print_holiday(
         counties,
         types,
     }: &Holiday,
+    calendar: Option<Calendar>,
 ) {
     let counties = comma_sep(&counties);
     let types = comma_sep(&types);
 
-    println!("{date} {name:40} {counties:25} {types}")
+    match calendar {
+        Some(calendar) => {
+            let converted = holidate::convert(*date, calendar);
+            println!("{date} [{converted}] {name:40} {counties:25} {types}")
+        }
+        None => println!("{date} {name:40} {counties:25} {types}"),
+    }
+}
+
+/// A flattened `Holiday` suitable for a single CSV record, the way the
+/// transit-data crates serialize their rows.
+#[derive(Debug, Serialize)]
+struct HolidayRecord {
+    date: Date,
+    name: String,
+    counties: String,
+    types: String,
+}
+
+impl From<&Holiday> for HolidayRecord {
+    fn from(holiday: &Holiday) -> Self {
+        HolidayRecord {
+            date: holiday.date,
+            name: holiday.name.clone(),
+            counties: comma_sep(&holiday.counties),
+            types: comma_sep(&holiday.types),
+        }
+    }
+}
+
+/// Write the holidays to stdout as CSV, one record per holiday plus a header.
+fn print_csv(holidays: &[Holiday]) -> color_eyre::eyre::Result<()> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    for holiday in holidays {
+        writer.serialize(HolidayRecord::from(holiday))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the holidays to stdout as an iCalendar `VCALENDAR`, one all-day
+/// `VEVENT` per holiday, so the output can be piped straight into a calendar
+/// app.
+fn print_ical(country_code: &str, holidays: &[Holiday]) {
+    let country = country_code.to_lowercase();
+    // every VEVENT carries the same DTSTAMP: the moment this calendar was
+    // produced, as a UTC timestamp.
+    let dtstamp = time::OffsetDateTime::now_utc()
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .expect("UTC timestamps always format");
+
+    // iCalendar requires CRLF line endings (RFC 5545 §3.1), so build the
+    // document explicitly rather than relying on `println!`.
+    let mut out = String::new();
+    let mut line = |contents: &str| {
+        out.push_str(contents);
+        out.push_str("\r\n");
+    };
+
+    line("BEGIN:VCALENDAR");
+    line("VERSION:2.0");
+    line("PRODID:-//holidate//EN");
+    for Holiday {
+        date,
+        name,
+        types,
+        ..
+    } in holidays
+    {
+        let stamp = date
+            .format(format_description!("[year][month][day]"))
+            .expect("dates always format as a basic date");
+        line("BEGIN:VEVENT");
+        line(&format!("UID:{stamp}-{country}-{}@holidate", ical_slug(name)));
+        line(&format!("DTSTAMP:{dtstamp}"));
+        line(&format!("DTSTART;VALUE=DATE:{stamp}"));
+        line(&format!("SUMMARY:{}", ical_escape(name)));
+        if !types.is_empty() {
+            let categories = types.iter().map(|ty| ical_escape(&ty.to_string())).join(",");
+            line(&format!("CATEGORIES:{categories}"));
+        }
+        line("END:VEVENT");
+    }
+    line("END:VCALENDAR");
+
+    print!("{out}");
+}
+
+/// Escape a string for use as an iCalendar TEXT value (RFC 5545 §3.3.11).
+fn ical_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\n' => escaped.push_str("\\n"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+/// Reduce a holiday name to an identifier-safe slug for use in a `UID`.
+fn ical_slug(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
 }
 
 fn main() -> color_eyre::eyre::Result<()> {
@@ -73,10 +259,38 @@ fn main() -> color_eyre::eyre::Result<()> {
     color_eyre::install()?;
 
     let options = Options::from_args();
-    for holiday in
-        holidate::next_holidays(&options.country_code, options.relative_to(), options.number)?
-    {
-        print_holiday(&holiday);
+    let cache = FsCache;
+
+    if let Some(Command::Serve { bind }) = &options.command {
+        return serve::serve(bind, &cache);
+    }
+
+    let country_code = options
+        .country_code
+        .as_deref()
+        .ok_or_else(|| color_eyre::eyre::eyre!("a country code is required"))?;
+    let filter = HolidayFilter {
+        types: options.types.clone(),
+        counties: options.county.clone(),
+        name_contains: options.name_contains.clone(),
+    };
+    let holidays = holidate::next_holidays(
+        &cache,
+        country_code,
+        options.relative_to(),
+        options.number,
+        &filter,
+    )?;
+
+    match options.format {
+        Format::Table => {
+            for holiday in &holidays {
+                print_holiday(holiday, options.calendar);
+            }
+        }
+        Format::Json => serde_json::to_writer_pretty(std::io::stdout(), &holidays)?,
+        Format::Csv => print_csv(&holidays)?,
+        Format::Ical => print_ical(country_code, &holidays),
     }
 
     Ok(())
@@ -0,0 +1,234 @@
+//! A small blocking HTTP service wrapping [`holidate::next_holidays`], so other
+//! apps can query holidays without reimplementing the Nager client or cache.
+
+use std::io::Cursor;
+
+use holidate::{Cache, HolidayFilter, CACHE_FADEOUT};
+use time::{macros::format_description, Date, OffsetDateTime};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Run the HTTP service on `bind`, serving holidays out of `cache`, until the
+/// process is killed.
+pub fn serve(bind: &str, cache: &dyn Cache) -> color_eyre::eyre::Result<()> {
+    let server = Server::http(bind).map_err(|err| color_eyre::eyre::eyre!(err))?;
+    eprintln!("holidate serving on http://{bind}");
+
+    for request in server.incoming_requests() {
+        if let Err(err) = handle(request, cache) {
+            // a failure to write the response back is all we can do nothing
+            // about; log it and carry on serving.
+            eprintln!("error responding to request: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Dispatch a single request, always sending exactly one response.
+fn handle(request: Request, cache: &dyn Cache) -> std::io::Result<()> {
+    // browsers send a CORS preflight before a cross-origin GET; answer it.
+    if request.method() == &Method::Options {
+        return respond(request, empty(204).with_cors());
+    }
+    if request.method() != &Method::Get {
+        return respond(request, json(405, r#"{"error":"method not allowed"}"#).with_cors());
+    }
+
+    let response = match route(request.url(), cache) {
+        Ok(outcome) => outcome,
+        Err(Rejection::NotFound) => json(404, r#"{"error":"not found"}"#),
+        Err(Rejection::BadRequest(msg)) => {
+            json(400, &format!(r#"{{"error":{}}}"#, quote(&msg)))
+        }
+        Err(Rejection::Upstream(err)) => {
+            let status = match err {
+                holidate::Error::UnknownCountry => 404,
+                _ => 500,
+            };
+            json(status, &format!(r#"{{"error":{}}}"#, quote(&err.to_string())))
+        }
+    };
+
+    respond(request, response.with_cors())
+}
+
+/// Why a request couldn't be served the holiday payload.
+enum Rejection {
+    NotFound,
+    BadRequest(String),
+    Upstream(holidate::Error),
+}
+
+impl From<holidate::Error> for Rejection {
+    fn from(err: holidate::Error) -> Self {
+        Rejection::Upstream(err)
+    }
+}
+
+/// Match `GET /holidays/{country}?from=YYYY-MM-DD&n=5` and build its response,
+/// including the conditional-request and caching headers.
+fn route(url: &str, cache: &dyn Cache) -> Result<OutResponse, Rejection> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    let country = path
+        .strip_prefix("/holidays/")
+        .filter(|rest| !rest.is_empty() && !rest.contains('/'))
+        .ok_or(Rejection::NotFound)?
+        .to_lowercase();
+
+    let mut from = OffsetDateTime::now_utc().date();
+    let mut n = 5usize;
+    for (key, value) in query_pairs(query) {
+        match key.as_str() {
+            "from" => {
+                from = Date::parse(&value, format_description!("[year]-[month]-[day]"))
+                    .map_err(|_| Rejection::BadRequest(format!("invalid from date {value:?}")))?;
+            }
+            "n" => {
+                n = value
+                    .parse()
+                    .map_err(|_| Rejection::BadRequest(format!("invalid n {value:?}")))?;
+            }
+            _ => {}
+        }
+    }
+
+    let holidays = holidate::next_holidays(cache, &country, from, n, &HolidayFilter::default())?;
+    let body = serde_json::to_string(&holidays)
+        .map_err(|err| Rejection::Upstream(err.into()))?;
+
+    // the cached page for the `from` year backs the caching headers: its
+    // `fetched` timestamp drives both the ETag and the remaining freshness.
+    let mut response = json(200, &body);
+    if let Some(cached) = cache.load_raw(from.year(), &country)? {
+        let etag = format!(
+            "\"{}-{}-{}\"",
+            country,
+            cached.year,
+            cached.fetched.unix_timestamp()
+        );
+        let remaining = (cached.fetched + CACHE_FADEOUT - OffsetDateTime::now_utc())
+            .whole_seconds()
+            .max(0);
+        response = response
+            .with_header("Cache-Control", &format!("public, max-age={remaining}"))
+            .with_header("ETag", &etag);
+    }
+    Ok(response)
+}
+
+/// Split a query string into decoded key/value pairs. We only ever read `from`
+/// and `n`, neither of which is percent-encoded in practice, so a minimal
+/// `+`-to-space and `%XX` decode is enough.
+fn query_pairs(query: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+}
+
+fn percent_decode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut bytes = input.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'+' => out.push(' '),
+            b'%' => {
+                let hi = bytes.next().and_then(|b| (b as char).to_digit(16));
+                let lo = bytes.next().and_then(|b| (b as char).to_digit(16));
+                if let (Some(hi), Some(lo)) = (hi, lo) {
+                    out.push(((hi * 16 + lo) as u8) as char);
+                }
+            }
+            other => out.push(other as char),
+        }
+    }
+    out
+}
+
+/// A response under construction, carrying its status, body, and headers until
+/// it's handed to `tiny_http`.
+struct OutResponse {
+    status: u16,
+    body: String,
+    headers: Vec<(String, String)>,
+}
+
+impl OutResponse {
+    fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Attach permissive CORS headers so browser clients can call the service.
+    fn with_cors(self) -> Self {
+        self.with_header("Access-Control-Allow-Origin", "*")
+            .with_header("Access-Control-Allow-Methods", "GET, OPTIONS")
+            .with_header("Access-Control-Allow-Headers", "If-None-Match")
+    }
+}
+
+fn json(status: u16, body: &str) -> OutResponse {
+    OutResponse {
+        status,
+        body: body.to_string(),
+        headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+    }
+}
+
+fn empty(status: u16) -> OutResponse {
+    OutResponse {
+        status,
+        body: String::new(),
+        headers: Vec::new(),
+    }
+}
+
+/// Send `out` in reply to `request`, downgrading to `304 Not Modified` when the
+/// client's `If-None-Match` already matches the response's `ETag`.
+fn respond(request: Request, out: OutResponse) -> std::io::Result<()> {
+    let etag = out
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("etag"))
+        .map(|(_, value)| value.clone());
+    let if_none_match = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("If-None-Match"))
+        .map(|header| header.value.as_str().to_string());
+
+    let not_modified = matches!((etag, if_none_match), (Some(tag), Some(seen)) if tag == seen);
+
+    let mut response = if not_modified {
+        Response::new(304.into(), Vec::new(), Cursor::new(Vec::new()), Some(0), None)
+    } else {
+        Response::new(
+            out.status.into(),
+            Vec::new(),
+            Cursor::new(out.body.into_bytes()),
+            None,
+            None,
+        )
+    };
+
+    for (name, value) in out.headers {
+        // skip the body content type on a 304, which carries no body.
+        if not_modified && name.eq_ignore_ascii_case("content-type") {
+            continue;
+        }
+        if let Ok(header) = Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+            response.add_header(header);
+        }
+    }
+
+    request.respond(response)
+}
+
+/// JSON-encode a string so it can be embedded as a value in a hand-built object.
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}